@@ -0,0 +1,240 @@
+//! 基于内容分块的备份子系统
+//!
+//! 使用滚动哈希（gear hash）对文件内容做内容定义分块（CDC），
+//! 按分块内容的 SHA-256 去重存储，每个快照只记录有序的分块哈希列表。
+//! 写入新版本时只需持久化尚未出现过的分块（"合并已知分块"），
+//! 大文件的小范围编辑因此只产生与改动相当的存储开销。
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// 读取源文件时使用的缓冲区大小
+const READ_BUF_SIZE: usize = 64 * 1024;
+
+/// 滚动哈希窗口大小（字节）
+const WINDOW: usize = 64;
+/// 最小分块大小：1 MiB
+const MIN_CHUNK: usize = 1 << 20;
+/// 最大分块大小：4 MiB
+const MAX_CHUNK: usize = 4 << 20;
+/// 低位掩码，期望平均分块大小约 2 MiB
+const CUT_MASK: u64 = (1 << 21) - 1;
+
+/// 分块用的 gear 表，按固定种子生成，保证跨平台/跨进程一致
+fn gear_table() -> &'static [u64; 256] {
+    static TABLE: OnceLock<[u64; 256]> = OnceLock::new();
+    TABLE.get_or_init(|| {
+        let mut table = [0u64; 256];
+        let mut seed: u64 = 0x9E3779B97F4A7C15;
+        for slot in table.iter_mut() {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            *slot = seed;
+        }
+        table
+    })
+}
+
+/// 流式地对读取器中的数据做内容定义分块，每凑够一个分块就调用 `on_chunk` 落盘，
+/// 全程只在内存里保留当前未切出的分块（最多 `MAX_CHUNK` 字节）和一个读缓冲区，
+/// 不会把整份源文件都载入内存——这对这个子系统要支撑的大资产增量备份是必要的。
+fn chunk_stream<R: Read>(
+    mut reader: R,
+    mut on_chunk: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<u64, String> {
+    let table = gear_table();
+    let mut current = Vec::with_capacity(MIN_CHUNK);
+    let mut hash: u64 = 0;
+    let mut total_size: u64 = 0;
+    let mut buf = [0u8; READ_BUF_SIZE];
+
+    loop {
+        let read = reader
+            .read(&mut buf)
+            .map_err(|e| format!("读取文件失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        for &byte in &buf[..read] {
+            current.push(byte);
+            hash = (hash << 1).wrapping_add(table[byte as usize]);
+            total_size += 1;
+
+            let len = current.len();
+            let past_window = len >= WINDOW;
+            if len >= MIN_CHUNK && (len >= MAX_CHUNK || (past_window && hash & CUT_MASK == 0)) {
+                on_chunk(&current)?;
+                current.clear();
+                hash = 0;
+            }
+        }
+    }
+    if !current.is_empty() {
+        on_chunk(&current)?;
+    }
+
+    Ok(total_size)
+}
+
+fn hash_chunk(chunk: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(chunk);
+    hex::encode(hasher.finalize())
+}
+
+fn chunks_dir(storage_path: &str) -> PathBuf {
+    Path::new(storage_path).join("chunks")
+}
+
+fn snapshots_dir(storage_path: &str) -> PathBuf {
+    Path::new(storage_path).join("snapshots")
+}
+
+fn snapshot_index_path(storage_path: &str, snapshot_name: &str) -> PathBuf {
+    snapshots_dir(storage_path).join(format!("{}.json", snapshot_name))
+}
+
+/// 一个快照的分块索引：有序分块哈希 + 原始总大小
+#[derive(Debug, Serialize, Deserialize)]
+struct ChunkIndex {
+    chunks: Vec<String>,
+    total_size: u64,
+}
+
+/// `backup_file` 的返回结果
+#[derive(Debug, Serialize)]
+pub struct BackupResult {
+    snapshot: String,
+    chunk_count: usize,
+    new_chunks: usize,
+    total_size: u64,
+}
+
+/// `gc_chunks` 的返回结果
+#[derive(Debug, Serialize)]
+pub struct GcResult {
+    removed: usize,
+    freed_bytes: u64,
+}
+
+/// 将文件内容分块、去重后写入快照存储
+#[tauri::command]
+pub fn backup_file(
+    storage_path: String,
+    file_path: String,
+    snapshot_name: String,
+) -> Result<BackupResult, String> {
+    let source = fs::File::open(&file_path).map_err(|e| format!("读取文件失败: {}", e))?;
+    let reader = BufReader::with_capacity(READ_BUF_SIZE, source);
+
+    let chunks_dir = chunks_dir(&storage_path);
+    fs::create_dir_all(&chunks_dir).map_err(|e| format!("创建分块目录失败: {}", e))?;
+    fs::create_dir_all(snapshots_dir(&storage_path))
+        .map_err(|e| format!("创建快照目录失败: {}", e))?;
+
+    let mut index = ChunkIndex {
+        chunks: Vec::new(),
+        total_size: 0,
+    };
+    let mut new_chunks = 0usize;
+
+    let total_size = chunk_stream(reader, |chunk| {
+        let hex_hash = hash_chunk(chunk);
+        let chunk_path = chunks_dir.join(&hex_hash);
+        if !chunk_path.exists() {
+            fs::write(&chunk_path, chunk).map_err(|e| format!("写入分块失败: {}", e))?;
+            new_chunks += 1;
+        }
+        index.chunks.push(hex_hash);
+        Ok(())
+    })?;
+    index.total_size = total_size;
+
+    let index_json =
+        serde_json::to_string_pretty(&index).map_err(|e| format!("序列化索引失败: {}", e))?;
+    fs::write(snapshot_index_path(&storage_path, &snapshot_name), index_json)
+        .map_err(|e| format!("写入快照索引失败: {}", e))?;
+
+    Ok(BackupResult {
+        snapshot: snapshot_name,
+        chunk_count: index.chunks.len(),
+        new_chunks,
+        total_size: index.total_size,
+    })
+}
+
+/// 依据快照索引重新拼接出原始文件
+#[tauri::command]
+pub fn restore_file(
+    storage_path: String,
+    snapshot_name: String,
+    output_path: String,
+) -> Result<(), String> {
+    let index_path = snapshot_index_path(&storage_path, &snapshot_name);
+    let index_json = fs::read_to_string(&index_path).map_err(|e| format!("读取快照索引失败: {}", e))?;
+    let index: ChunkIndex =
+        serde_json::from_str(&index_json).map_err(|e| format!("解析快照索引失败: {}", e))?;
+
+    let chunks_dir = chunks_dir(&storage_path);
+    let mut data = Vec::with_capacity(index.total_size as usize);
+    for hex_hash in &index.chunks {
+        let chunk = fs::read(chunks_dir.join(hex_hash))
+            .map_err(|e| format!("读取分块 {} 失败: {}", hex_hash, e))?;
+        data.extend_from_slice(&chunk);
+    }
+
+    if let Some(parent) = PathBuf::from(&output_path).parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+    }
+    fs::write(&output_path, data).map_err(|e| format!("写入文件失败: {}", e))
+}
+
+/// 删除不再被任何快照索引引用的分块文件
+#[tauri::command]
+pub fn gc_chunks(storage_path: String) -> Result<GcResult, String> {
+    let chunks_dir = chunks_dir(&storage_path);
+    if !chunks_dir.exists() {
+        return Ok(GcResult {
+            removed: 0,
+            freed_bytes: 0,
+        });
+    }
+
+    let mut referenced: HashSet<String> = HashSet::new();
+    let snapshots_dir = snapshots_dir(&storage_path);
+    if snapshots_dir.exists() {
+        let entries = fs::read_dir(&snapshots_dir).map_err(|e| format!("读取快照目录失败: {}", e))?;
+        for entry in entries.flatten() {
+            let content = fs::read_to_string(entry.path())
+                .map_err(|e| format!("读取快照索引失败: {}", e))?;
+            let index: ChunkIndex =
+                serde_json::from_str(&content).map_err(|e| format!("解析快照索引失败: {}", e))?;
+            referenced.extend(index.chunks);
+        }
+    }
+
+    let mut removed = 0usize;
+    let mut freed_bytes = 0u64;
+    let entries = fs::read_dir(&chunks_dir).map_err(|e| format!("读取分块目录失败: {}", e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !referenced.contains(&name) {
+            if let Ok(metadata) = entry.metadata() {
+                freed_bytes += metadata.len();
+            }
+            fs::remove_file(entry.path()).map_err(|e| format!("删除分块失败: {}", e))?;
+            removed += 1;
+        }
+    }
+
+    Ok(GcResult {
+        removed,
+        freed_bytes,
+    })
+}
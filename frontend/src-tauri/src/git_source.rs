@@ -0,0 +1,153 @@
+//! Git 仓库内容源
+//!
+//! 允许用户把远程 Git 仓库登记为内容源，并物化到 Pixcore 存储目录下的
+//! `sources/<name>`。首次注册时克隆（未固定 revision 时为浅克隆），之后的更新按固定 revision 检出
+//! 或跟随分支做快进式拉取，便于以可复现的方式导入外部资源/提示词库，
+//! 而不是手动复制文件。
+
+use git2::build::RepoBuilder;
+use git2::{FetchOptions, Repository};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 注册一个 Git 内容源时的参数
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GitSourceSpec {
+    url: String,
+    branch: Option<String>,
+    revision: Option<String>,
+}
+
+/// 克隆/更新后返回给前端的结果，用于展示来源信息
+#[derive(Debug, Serialize)]
+pub struct GitSourceResult {
+    name: String,
+    local_path: String,
+    resolved_commit: String,
+}
+
+fn validate_spec(spec: &GitSourceSpec) -> Result<(), String> {
+    if spec.branch.is_some() && spec.revision.is_some() {
+        return Err("branch 和 revision 不能同时指定".to_string());
+    }
+    Ok(())
+}
+
+fn source_path(storage_path: &str, name: &str) -> PathBuf {
+    Path::new(storage_path).join("sources").join(name)
+}
+
+fn head_commit_hash(repo: &Repository) -> Result<String, String> {
+    let head = repo.head().map_err(|e| format!("获取 HEAD 失败: {}", e))?;
+    let commit = head
+        .peel_to_commit()
+        .map_err(|e| format!("解析提交失败: {}", e))?;
+    Ok(commit.id().to_string())
+}
+
+/// 首次注册并克隆一个 Git 内容源；未固定 revision 时做浅克隆，固定 revision 时做完整克隆
+#[tauri::command]
+pub fn git_clone_source(
+    storage_path: String,
+    name: String,
+    spec: GitSourceSpec,
+) -> Result<GitSourceResult, String> {
+    validate_spec(&spec)?;
+
+    let local_path = source_path(&storage_path, &name);
+    if let Some(parent) = local_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建内容源目录失败: {}", e))?;
+    }
+    if local_path.exists() {
+        return Err(format!("内容源 {} 已存在，请使用 git_update_source", name));
+    }
+
+    let mut fetch_options = FetchOptions::new();
+    // 固定 revision 时不能做浅克隆：depth(1) 只包含被克隆分支的尖端提交，
+    // 之后 `revparse_single(revision)` 多半解析不到被钉住的历史提交。
+    if spec.revision.is_none() {
+        fetch_options.depth(1);
+    }
+
+    let mut builder = RepoBuilder::new();
+    builder.fetch_options(fetch_options);
+    if let Some(branch) = &spec.branch {
+        builder.branch(branch);
+    }
+
+    let repo = builder
+        .clone(&spec.url, &local_path)
+        .map_err(|e| format!("克隆仓库失败: {}", e))?;
+
+    if let Some(revision) = &spec.revision {
+        checkout_revision(&repo, revision)?;
+    }
+
+    let resolved_commit = head_commit_hash(&repo)?;
+    Ok(GitSourceResult {
+        name,
+        local_path: local_path.to_string_lossy().to_string(),
+        resolved_commit,
+    })
+}
+
+fn checkout_revision(repo: &Repository, revision: &str) -> Result<(), String> {
+    let object = repo
+        .revparse_single(revision)
+        .map_err(|e| format!("解析 revision 失败: {}", e))?;
+    repo.checkout_tree(&object, None)
+        .map_err(|e| format!("检出 revision 失败: {}", e))?;
+    repo.set_head_detached(object.id())
+        .map_err(|e| format!("切换 HEAD 失败: {}", e))
+}
+
+fn fast_forward_branch(repo: &Repository, branch: &str) -> Result<(), String> {
+    repo.find_remote("origin")
+        .and_then(|mut remote| remote.fetch(&[branch], None, None))
+        .map_err(|e| format!("拉取远程分支失败: {}", e))?;
+
+    let fetch_head = repo
+        .find_reference("FETCH_HEAD")
+        .map_err(|e| format!("读取 FETCH_HEAD 失败: {}", e))?;
+    let fetch_commit = repo
+        .reference_to_annotated_commit(&fetch_head)
+        .map_err(|e| format!("解析 FETCH_HEAD 失败: {}", e))?;
+
+    let fetch_object = repo
+        .find_object(fetch_commit.id(), None)
+        .map_err(|e| format!("查找提交对象失败: {}", e))?;
+    repo.checkout_tree(&fetch_object, None)
+        .map_err(|e| format!("检出分支失败: {}", e))?;
+    repo.set_head_detached(fetch_commit.id())
+        .map_err(|e| format!("切换 HEAD 失败: {}", e))
+}
+
+/// 更新已登记的 Git 内容源：按固定 revision 检出，或快进式跟随分支
+#[tauri::command]
+pub fn git_update_source(
+    storage_path: String,
+    name: String,
+    spec: GitSourceSpec,
+) -> Result<GitSourceResult, String> {
+    validate_spec(&spec)?;
+
+    let local_path = source_path(&storage_path, &name);
+    let repo = Repository::open(&local_path).map_err(|e| format!("打开本地仓库失败: {}", e))?;
+
+    if let Some(revision) = &spec.revision {
+        repo.find_remote("origin")
+            .and_then(|mut remote| remote.fetch(&[] as &[&str], None, None))
+            .map_err(|e| format!("拉取远程仓库失败: {}", e))?;
+        checkout_revision(&repo, revision)?;
+    } else {
+        let branch = spec.branch.clone().unwrap_or_else(|| "HEAD".to_string());
+        fast_forward_branch(&repo, &branch)?;
+    }
+
+    let resolved_commit = head_commit_hash(&repo)?;
+    Ok(GitSourceResult {
+        name,
+        local_path: local_path.to_string_lossy().to_string(),
+        resolved_commit,
+    })
+}
@@ -0,0 +1,165 @@
+//! 无系统密钥链环境下的加密文件密钥库
+//!
+//! 部分平台（尤其是无 Secret Service 的 Linux headless 环境）没有可用的系统密钥链，
+//! `keyring::Entry` 会直接失败。这里提供一个基于口令派生密钥的 AEAD 加密文件后备方案：
+//! 每条密钥单独存成一个文件，文件内容为 JSON 编码的 salt + nonce + 密文，
+//! 并在 Unix 上将文件权限收紧为仅所有者可读写（0600）。
+//!
+//! `store_api_key` 等命令优先使用系统密钥链，失败时透明回落到本方案。
+
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use pbkdf2::pbkdf2_hmac;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const PBKDF2_ROUNDS: u32 = 600_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// 单条加密密钥在磁盘上的表示
+#[derive(Debug, Serialize, Deserialize)]
+struct EncryptedEntry {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn keystore_dir(storage_path: &str) -> PathBuf {
+    Path::new(storage_path).join("keystore")
+}
+
+fn entry_path(storage_path: &str, provider: &str) -> PathBuf {
+    keystore_dir(storage_path).join(format!("{}.json", provider))
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; 32] {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// 以仅所有者可读写（0600）的权限原子地创建密钥文件，镜像账户密钥库对密钥文件的权限收紧做法。
+/// 权限在 `open` 调用中随文件创建一起生效，不会留出"先写入、后 chmod"的窗口期。
+#[cfg(unix)]
+fn create_owner_only_file(path: &Path) -> Result<fs::File, String> {
+    use std::os::unix::fs::OpenOptionsExt;
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)
+        .map_err(|e| format!("创建密钥文件失败: {}", e))
+}
+
+#[cfg(not(unix))]
+fn create_owner_only_file(path: &Path) -> Result<fs::File, String> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(path)
+        .map_err(|e| format!("创建密钥文件失败: {}", e))
+}
+
+/// 将密钥加密写入文件密钥库
+pub fn store_encrypted(
+    storage_path: &str,
+    provider: &str,
+    passphrase: &str,
+    api_key: &str,
+) -> Result<(), String> {
+    let dir = keystore_dir(storage_path);
+    fs::create_dir_all(&dir).map_err(|e| format!("创建密钥库目录失败: {}", e))?;
+
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let mut nonce_bytes = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut nonce_bytes);
+
+    let key_bytes = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let ciphertext = cipher
+        .encrypt(nonce, api_key.as_bytes())
+        .map_err(|e| format!("加密密钥失败: {}", e))?;
+
+    let entry = EncryptedEntry {
+        salt: hex::encode(salt),
+        nonce: hex::encode(nonce_bytes),
+        ciphertext: hex::encode(ciphertext),
+    };
+    let json = serde_json::to_string(&entry).map_err(|e| format!("序列化密钥条目失败: {}", e))?;
+
+    let path = entry_path(storage_path, provider);
+    let mut file = create_owner_only_file(&path)?;
+    file.write_all(json.as_bytes())
+        .map_err(|e| format!("写入密钥文件失败: {}", e))
+}
+
+/// 从文件密钥库解密读取密钥
+pub fn get_encrypted(
+    storage_path: &str,
+    provider: &str,
+    passphrase: &str,
+) -> Result<Option<String>, String> {
+    let path = entry_path(storage_path, provider);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let json = fs::read_to_string(&path).map_err(|e| format!("读取密钥文件失败: {}", e))?;
+    let entry: EncryptedEntry =
+        serde_json::from_str(&json).map_err(|e| format!("解析密钥文件失败: {}", e))?;
+
+    let salt = hex::decode(&entry.salt).map_err(|e| format!("解码 salt 失败: {}", e))?;
+    let nonce_bytes = hex::decode(&entry.nonce).map_err(|e| format!("解码 nonce 失败: {}", e))?;
+    let ciphertext = hex::decode(&entry.ciphertext).map_err(|e| format!("解码密文失败: {}", e))?;
+
+    // 密钥文件可能被截断/手改/磁盘损坏，长度对不上时要干净地报错；
+    // 否则 nonce 长度不对会让 `Nonce::from_slice` 的长度断言直接 panic 整个后端进程。
+    if salt.len() != SALT_LEN {
+        return Err(format!(
+            "密钥文件已损坏：salt 长度应为 {} 字节，实际为 {} 字节",
+            SALT_LEN,
+            salt.len()
+        ));
+    }
+    if nonce_bytes.len() != NONCE_LEN {
+        return Err(format!(
+            "密钥文件已损坏：nonce 长度应为 {} 字节，实际为 {} 字节",
+            NONCE_LEN,
+            nonce_bytes.len()
+        ));
+    }
+
+    let key_bytes = derive_key(passphrase, &salt);
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key_bytes));
+    let nonce = Nonce::from_slice(&nonce_bytes);
+    let plaintext = cipher
+        .decrypt(nonce, ciphertext.as_ref())
+        .map_err(|_| "解密密钥失败：口令错误或文件已损坏".to_string())?;
+
+    String::from_utf8(plaintext)
+        .map(Some)
+        .map_err(|e| format!("密钥内容不是合法 UTF-8: {}", e))
+}
+
+/// 从文件密钥库中删除一条密钥
+pub fn delete_encrypted(storage_path: &str, provider: &str) -> Result<(), String> {
+    let path = entry_path(storage_path, provider);
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("删除密钥文件失败: {}", e))?;
+    }
+    Ok(())
+}
+
+/// 文件密钥库中是否存在该条目（无需口令即可判断是否存在）
+pub fn has_encrypted(storage_path: &str, provider: &str) -> bool {
+    entry_path(storage_path, provider).exists()
+}
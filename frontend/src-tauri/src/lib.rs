@@ -1,6 +1,17 @@
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fs;
-use std::path::PathBuf;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+mod backup;
+mod git_source;
+mod keystore;
+mod watcher;
+
+use backup::{backup_file, gc_chunks, restore_file};
+use git_source::{git_clone_source, git_update_source};
+use watcher::{unwatch_path, watch_path, WatcherState};
 
 // ============ 文件系统相关命令 ============
 
@@ -49,14 +60,91 @@ fn read_file(path: String) -> Result<Vec<u8>, String> {
     fs::read(&path).map_err(|e| format!("读取文件失败: {}", e))
 }
 
-/// 写入文件
+/// 写入文件。若提供 `expected_hash`，则走原子的"写临时文件 -> fsync -> 校验哈希 -> rename"
+/// 流程提交，哈希不匹配或中途出错都会回滚临时文件，避免写坏/写半截的文件落地。
 #[tauri::command]
-fn write_file(path: String, contents: Vec<u8>) -> Result<(), String> {
-    // 确保父目录存在
-    if let Some(parent) = PathBuf::from(&path).parent() {
+fn write_file(path: String, contents: Vec<u8>, expected_hash: Option<String>) -> Result<(), String> {
+    let target = PathBuf::from(&path);
+    if let Some(parent) = target.parent() {
         fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
     }
-    fs::write(&path, contents).map_err(|e| format!("写入文件失败: {}", e))
+
+    match expected_hash {
+        None => fs::write(&target, contents).map_err(|e| format!("写入文件失败: {}", e)),
+        Some(expected_hash) => write_file_verified(&target, &contents, &expected_hash),
+    }
+}
+
+fn sibling_tmp_path(target: &Path) -> PathBuf {
+    let mut name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    name.push_str(".pixcore_tmp");
+    target.with_file_name(name)
+}
+
+fn write_file_verified(target: &Path, contents: &[u8], expected_hash: &str) -> Result<(), String> {
+    let tmp_path = sibling_tmp_path(target);
+
+    let write_result = (|| -> Result<(), String> {
+        let mut file = fs::File::create(&tmp_path).map_err(|e| format!("创建临时文件失败: {}", e))?;
+        file.write_all(contents)
+            .map_err(|e| format!("写入临时文件失败: {}", e))?;
+        file.sync_all().map_err(|e| format!("同步临时文件失败: {}", e))
+    })();
+    if let Err(e) = write_result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    let actual_hash = match hash_file_path(&tmp_path) {
+        Ok(hash) => hash,
+        Err(e) => {
+            let _ = fs::remove_file(&tmp_path);
+            return Err(e);
+        }
+    };
+    if actual_hash != expected_hash {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(format!(
+            "写入内容哈希不匹配，期望 {}，实际 {}",
+            expected_hash, actual_hash
+        ));
+    }
+
+    fs::rename(&tmp_path, target).map_err(|e| {
+        let _ = fs::remove_file(&tmp_path);
+        format!("提交文件失败: {}", e)
+    })
+}
+
+/// 流式计算文件的 SHA-256，大文件也不会一次性载入内存
+fn hash_file_path(path: &Path) -> Result<String, String> {
+    let mut file = fs::File::open(path).map_err(|e| format!("打开文件失败: {}", e))?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 65536];
+    loop {
+        let read = file.read(&mut buf).map_err(|e| format!("读取文件失败: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// 计算文件的 SHA-256 十六进制摘要
+#[tauri::command]
+fn hash_file(path: String) -> Result<String, String> {
+    hash_file_path(Path::new(&path))
+}
+
+/// 重新计算文件摘要并与给定的期望值比对，用于检测篡改或下载不完整
+#[tauri::command]
+fn verify_file(path: String, expected_hash: String) -> Result<bool, String> {
+    let actual_hash = hash_file_path(Path::new(&path))?;
+    Ok(actual_hash == expected_hash)
 }
 
 /// 删除文件
@@ -65,7 +153,7 @@ fn delete_file(path: String) -> Result<(), String> {
     fs::remove_file(&path).map_err(|e| format!("删除文件失败: {}", e))
 }
 
-/// 列出目录内容
+/// 列出目录内容（仅当前层级）
 #[tauri::command]
 fn list_directory(path: String) -> Result<Vec<FileInfo>, String> {
     let path = PathBuf::from(&path);
@@ -79,12 +167,12 @@ fn list_directory(path: String) -> Result<Vec<FileInfo>, String> {
     for entry in entries {
         if let Ok(entry) = entry {
             let metadata = entry.metadata().ok();
-            files.push(FileInfo {
-                name: entry.file_name().to_string_lossy().to_string(),
-                path: entry.path().to_string_lossy().to_string(),
-                is_directory: metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false),
-                size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
-            });
+            files.push(build_file_info(
+                entry.file_name().to_string_lossy().to_string(),
+                entry.path().to_string_lossy().to_string(),
+                metadata,
+                None,
+            ));
         }
     }
     Ok(files)
@@ -95,7 +183,171 @@ struct FileInfo {
     name: String,
     path: String,
     is_directory: bool,
-    size: u64,
+    /// 文件大小；目录默认不填，仅在 `scan_directory` 开启递归统计时才有值
+    size: Option<u64>,
+    /// 最后修改时间（Unix 毫秒）
+    modified: Option<u64>,
+    /// 创建时间（Unix 毫秒），部分平台/文件系统不支持
+    created: Option<u64>,
+    readonly: bool,
+}
+
+fn system_time_millis(time: std::time::SystemTime) -> Option<u64> {
+    time.duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+fn build_file_info(
+    name: String,
+    path: String,
+    metadata: Option<fs::Metadata>,
+    dir_size: Option<u64>,
+) -> FileInfo {
+    let is_directory = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+    FileInfo {
+        name,
+        path,
+        is_directory,
+        size: if is_directory {
+            dir_size
+        } else {
+            metadata.as_ref().map(|m| m.len())
+        },
+        modified: metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .and_then(system_time_millis),
+        created: metadata
+            .as_ref()
+            .and_then(|m| m.created().ok())
+            .and_then(system_time_millis),
+        readonly: metadata
+            .as_ref()
+            .map(|m| m.permissions().readonly())
+            .unwrap_or(false),
+    }
+}
+
+/// 递归统计目录下所有文件的总大小
+fn directory_size(path: &Path) -> u64 {
+    let mut total = 0u64;
+    if let Ok(entries) = fs::read_dir(path) {
+        for entry in entries.flatten() {
+            if let Ok(metadata) = entry.metadata() {
+                if metadata.is_dir() {
+                    total += directory_size(&entry.path());
+                } else {
+                    total += metadata.len();
+                }
+            }
+        }
+    }
+    total
+}
+
+fn compile_patterns(patterns: &Option<Vec<String>>) -> Result<Vec<glob::Pattern>, String> {
+    match patterns {
+        None => Ok(Vec::new()),
+        Some(patterns) => patterns
+            .iter()
+            .map(|p| glob::Pattern::new(p).map_err(|e| format!("非法 glob 模式 {}: {}", p, e)))
+            .collect(),
+    }
+}
+
+fn scan_dir_recursive(
+    base: &Path,
+    current: &Path,
+    depth: usize,
+    max_depth: usize,
+    include: &[glob::Pattern],
+    exclude: &[glob::Pattern],
+    sum_directory_sizes: bool,
+    out: &mut Vec<FileInfo>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(current).map_err(|e| format!("读取目录失败: {}", e))?;
+    for entry in entries.flatten() {
+        let entry_path = entry.path();
+        let relative = entry_path
+            .strip_prefix(base)
+            .unwrap_or(&entry_path)
+            .to_string_lossy()
+            .to_string();
+        let metadata = entry.metadata().ok();
+        let is_directory = metadata.as_ref().map(|m| m.is_dir()).unwrap_or(false);
+
+        let included = include.is_empty() || include.iter().any(|pat| pat.matches(&relative));
+        let excluded = exclude.iter().any(|pat| pat.matches(&relative));
+        if included && !excluded {
+            let dir_size = if is_directory && sum_directory_sizes {
+                Some(directory_size(&entry_path))
+            } else {
+                None
+            };
+            out.push(build_file_info(
+                entry.file_name().to_string_lossy().to_string(),
+                relative,
+                metadata,
+                dir_size,
+            ));
+        }
+
+        // 目录一旦命中 exclude，就不再下钻，避免扫描 node_modules 这类大而无关的子树
+        if is_directory && !excluded && depth < max_depth {
+            scan_dir_recursive(
+                base,
+                &entry_path,
+                depth + 1,
+                max_depth,
+                include,
+                exclude,
+                sum_directory_sizes,
+                out,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// 递归扫描目录，支持最大深度、include/exclude glob 过滤与排序，
+/// 返回带相对路径的扁平列表，便于前端一次性渲染目录树或做过滤。
+#[tauri::command]
+fn scan_directory(
+    path: String,
+    max_depth: Option<usize>,
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    sort_by: Option<String>,
+    sum_directory_sizes: Option<bool>,
+) -> Result<Vec<FileInfo>, String> {
+    let base = PathBuf::from(&path);
+    if !base.exists() {
+        return Ok(vec![]);
+    }
+
+    let include_patterns = compile_patterns(&include)?;
+    let exclude_patterns = compile_patterns(&exclude)?;
+
+    let mut results = Vec::new();
+    scan_dir_recursive(
+        &base,
+        &base,
+        0,
+        max_depth.unwrap_or(usize::MAX),
+        &include_patterns,
+        &exclude_patterns,
+        sum_directory_sizes.unwrap_or(false),
+        &mut results,
+    )?;
+
+    match sort_by.as_deref() {
+        Some("size") => results.sort_by(|a, b| b.size.unwrap_or(0).cmp(&a.size.unwrap_or(0))),
+        Some("mtime") => results.sort_by(|a, b| b.modified.unwrap_or(0).cmp(&a.modified.unwrap_or(0))),
+        _ => results.sort_by(|a, b| a.name.cmp(&b.name)),
+    }
+
+    Ok(results)
 }
 
 /// 检查文件是否存在
@@ -115,21 +367,21 @@ fn get_file_size(path: String) -> Result<u64, String> {
 
 const KEYRING_SERVICE: &str = "com.pixcore.app";
 
-/// 存储 API Key 到系统密钥链
-#[tauri::command]
-fn store_api_key(provider: String, api_key: String) -> Result<(), String> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, &provider)
+// 系统密钥链不可用时（例如无 Secret Service 的 Linux headless 环境），
+// 下面几个命令会透明回落到 `keystore` 模块提供的加密文件密钥库。
+// `store_api_key`/`get_api_key` 的回落需要调用方同时提供 `storage_path` 与 `passphrase`，
+// `delete_api_key`/`has_api_key` 的回落只需要 `storage_path`。
+
+fn try_store_keyring(provider: &str, api_key: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider)
         .map_err(|e| format!("创建密钥条目失败: {}", e))?;
     entry
-        .set_password(&api_key)
-        .map_err(|e| format!("存储密钥失败: {}", e))?;
-    Ok(())
+        .set_password(api_key)
+        .map_err(|e| format!("存储密钥失败: {}", e))
 }
 
-/// 从系统密钥链获取 API Key
-#[tauri::command]
-fn get_api_key(provider: String) -> Result<Option<String>, String> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, &provider)
+fn try_get_keyring(provider: &str) -> Result<Option<String>, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider)
         .map_err(|e| format!("创建密钥条目失败: {}", e))?;
     match entry.get_password() {
         Ok(password) => Ok(Some(password)),
@@ -138,10 +390,8 @@ fn get_api_key(provider: String) -> Result<Option<String>, String> {
     }
 }
 
-/// 删除 API Key
-#[tauri::command]
-fn delete_api_key(provider: String) -> Result<(), String> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, &provider)
+fn try_delete_keyring(provider: &str) -> Result<(), String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider)
         .map_err(|e| format!("创建密钥条目失败: {}", e))?;
     match entry.delete_credential() {
         Ok(_) => Ok(()),
@@ -150,10 +400,8 @@ fn delete_api_key(provider: String) -> Result<(), String> {
     }
 }
 
-/// 检查 API Key 是否存在
-#[tauri::command]
-fn has_api_key(provider: String) -> Result<bool, String> {
-    let entry = keyring::Entry::new(KEYRING_SERVICE, &provider)
+fn try_has_keyring(provider: &str) -> Result<bool, String> {
+    let entry = keyring::Entry::new(KEYRING_SERVICE, provider)
         .map_err(|e| format!("创建密钥条目失败: {}", e))?;
     match entry.get_password() {
         Ok(_) => Ok(true),
@@ -162,6 +410,79 @@ fn has_api_key(provider: String) -> Result<bool, String> {
     }
 }
 
+/// 存储 API Key：优先系统密钥链，不可用时回落到加密文件密钥库
+#[tauri::command]
+fn store_api_key(
+    provider: String,
+    api_key: String,
+    storage_path: Option<String>,
+    passphrase: Option<String>,
+) -> Result<(), String> {
+    match try_store_keyring(&provider, &api_key) {
+        Ok(()) => Ok(()),
+        Err(keyring_err) => match (storage_path, passphrase) {
+            (Some(storage_path), Some(passphrase)) => {
+                keystore::store_encrypted(&storage_path, &provider, &passphrase, &api_key)
+            }
+            _ => Err(keyring_err),
+        },
+    }
+}
+
+/// 获取 API Key：系统密钥链中找不到时，不代表两个后端都没有，
+/// 只要调用方提供了 `storage_path`/`passphrase` 就继续查一遍加密文件密钥库，
+/// 这样两个后端才不会互相矛盾（例如密钥曾在密钥链临时故障时只写入了文件密钥库）。
+#[tauri::command]
+fn get_api_key(
+    provider: String,
+    storage_path: Option<String>,
+    passphrase: Option<String>,
+) -> Result<Option<String>, String> {
+    let keyring_result = try_get_keyring(&provider);
+    let keyring_found = matches!(keyring_result, Ok(Some(_)));
+    if keyring_found {
+        return keyring_result;
+    }
+
+    match (storage_path, passphrase) {
+        (Some(storage_path), Some(passphrase)) => {
+            keystore::get_encrypted(&storage_path, &provider, &passphrase)
+        }
+        _ => keyring_result,
+    }
+}
+
+/// 删除 API Key：两个后端都尝试删除，避免密钥链删除成功后文件密钥库里还残留一份
+#[tauri::command]
+fn delete_api_key(provider: String, storage_path: Option<String>) -> Result<(), String> {
+    let keyring_result = try_delete_keyring(&provider);
+
+    match storage_path {
+        Some(storage_path) => {
+            keystore::delete_encrypted(&storage_path, &provider)?;
+            // 与其他命令一致：密钥链出错视为不可用，文件密钥库删除成功即算成功
+            Ok(())
+        }
+        None => keyring_result,
+    }
+}
+
+/// 检查 API Key 是否存在：密钥链中没有不代表两个后端都没有，
+/// 提供了 `storage_path` 时继续查一遍加密文件密钥库。
+#[tauri::command]
+fn has_api_key(provider: String, storage_path: Option<String>) -> Result<bool, String> {
+    let keyring_result = try_has_keyring(&provider);
+    let keyring_found = matches!(keyring_result, Ok(true));
+    if keyring_found {
+        return keyring_result;
+    }
+
+    match storage_path {
+        Some(storage_path) => Ok(keystore::has_encrypted(&storage_path, &provider)),
+        None => keyring_result,
+    }
+}
+
 // ============ 系统信息相关命令 ============
 
 #[derive(Debug, Serialize)]
@@ -193,6 +514,7 @@ pub fn run() {
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_os::init())
         .plugin(tauri_plugin_store::Builder::default().build())
+        .manage(WatcherState::default())
         .invoke_handler(tauri::generate_handler![
             // 文件系统
             get_default_storage_path,
@@ -202,8 +524,21 @@ pub fn run() {
             write_file,
             delete_file,
             list_directory,
+            scan_directory,
             file_exists,
             get_file_size,
+            hash_file,
+            verify_file,
+            // 分块备份
+            backup_file,
+            restore_file,
+            gc_chunks,
+            // Git 内容源
+            git_clone_source,
+            git_update_source,
+            // 文件监听
+            watch_path,
+            unwatch_path,
             // 密钥存储
             store_api_key,
             get_api_key,
@@ -0,0 +1,111 @@
+//! 文件/目录变更监听
+//!
+//! 基于 `notify` 监听文件或目录的外部改动，去抖合并短时间内的重复事件后
+//! 通过 Tauri 事件（`file-created`/`file-changed`/`file-removed`）推送给前端，
+//! 这样前端可以在存储目录被其他进程修改时自动刷新，而不用靠轮询
+//! `file_exists`/`list_directory`。
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// 去抖窗口：窗口内针对同一路径的多次事件只触发一次推送
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// 按路径跟踪的活跃监听器，随应用状态管理，便于干净地拆卸
+#[derive(Default)]
+pub struct WatcherState {
+    watchers: Mutex<HashMap<String, RecommendedWatcher>>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FileChangeEvent {
+    path: String,
+}
+
+fn event_name(kind: &EventKind) -> Option<&'static str> {
+    match kind {
+        EventKind::Create(_) => Some("file-created"),
+        EventKind::Modify(_) => Some("file-changed"),
+        EventKind::Remove(_) => Some("file-removed"),
+        _ => None,
+    }
+}
+
+/// 开始监听一个文件或目录，变更事件去抖后推送给前端
+#[tauri::command]
+pub fn watch_path(
+    app: AppHandle,
+    state: tauri::State<WatcherState>,
+    path: String,
+) -> Result<(), String> {
+    let mut watchers = state
+        .watchers
+        .lock()
+        .map_err(|_| "获取监听器锁失败".to_string())?;
+    if watchers.contains_key(&path) {
+        return Ok(());
+    }
+
+    let (tx, rx) = channel::<notify::Result<Event>>();
+    let mut watcher =
+        notify::recommended_watcher(tx).map_err(|e| format!("创建文件监听器失败: {}", e))?;
+    watcher
+        .watch(Path::new(&path), RecursiveMode::Recursive)
+        .map_err(|e| format!("启动监听失败: {}", e))?;
+
+    let app_handle = app.clone();
+    std::thread::spawn(move || {
+        let mut pending: HashMap<PathBuf, (&'static str, Instant)> = HashMap::new();
+        loop {
+            match rx.recv_timeout(DEBOUNCE) {
+                Ok(Ok(event)) => {
+                    if let Some(name) = event_name(&event.kind) {
+                        for changed_path in event.paths {
+                            pending.insert(changed_path, (name, Instant::now()));
+                        }
+                    }
+                }
+                Ok(Err(_)) => {}
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+
+            let now = Instant::now();
+            let ready: Vec<PathBuf> = pending
+                .iter()
+                .filter(|(_, (_, seen_at))| now.duration_since(*seen_at) >= DEBOUNCE)
+                .map(|(changed_path, _)| changed_path.clone())
+                .collect();
+            for changed_path in ready {
+                if let Some((name, _)) = pending.remove(&changed_path) {
+                    let _ = app_handle.emit(
+                        name,
+                        FileChangeEvent {
+                            path: changed_path.to_string_lossy().to_string(),
+                        },
+                    );
+                }
+            }
+        }
+    });
+
+    watchers.insert(path, watcher);
+    Ok(())
+}
+
+/// 停止监听一个路径，拆除对应的底层监听器
+#[tauri::command]
+pub fn unwatch_path(state: tauri::State<WatcherState>, path: String) -> Result<(), String> {
+    let mut watchers = state
+        .watchers
+        .lock()
+        .map_err(|_| "获取监听器锁失败".to_string())?;
+    watchers.remove(&path);
+    Ok(())
+}